@@ -0,0 +1,281 @@
+use std::{
+    collections::HashMap,
+    ffi::{c_int, c_long, c_uchar, c_uint, c_ulong},
+    ptr,
+    sync::atomic::{AtomicBool, Ordering},
+    sync::mpsc::{self, Sender},
+    thread::{self, JoinHandle},
+};
+
+const GRAB_MODE_ASYNC: c_int = 1;
+const KEY_PRESS: c_int = 2;
+
+// Lock modifiers that get OR'd into an XKeyEvent's `state` whenever they
+// happen to be engaged, but don't change which key combination the user
+// meant to press. `XGrabKey` only matches an exact modifier state, so every
+// combination of these has to be grabbed too, or the hotkey silently never
+// fires whenever Caps Lock/Num Lock is on.
+const LOCK_MASK: KeyButMask = 1 << 1; // CapsLock
+const MOD2_MASK: KeyButMask = 1 << 4; // NumLock, on most layouts
+const IGNORED_LOCKS: [KeyButMask; 4] = [0, LOCK_MASK, MOD2_MASK, LOCK_MASK | MOD2_MASK];
+
+type Display = *const std::ffi::c_void;
+type Window = c_ulong;
+type KeyCode = c_uint;
+type KeyButMask = c_uint;
+
+#[repr(C)]
+struct XKeyEvent {
+    _type: c_int,
+    serial: c_ulong,
+    send_event: c_int,
+    display: Display,
+    window: Window,
+    root: Window,
+    subwindow: Window,
+    time: c_ulong,
+    x: c_int,
+    y: c_int,
+    x_root: c_int,
+    y_root: c_int,
+    state: c_uint,
+    keycode: c_uint,
+    same_screen: c_int,
+}
+
+// The real Xlib `XEvent` is a C union big enough to hold any event struct,
+// padded out to 24 `long`s; every event-receiving call (`XNextEvent`
+// included) needs a buffer of this size, not just the specific struct
+// (`XKeyEvent` here) the caller happens to be interested in, or it
+// overflows the stack.
+#[repr(C)]
+union XEvent {
+    type_: c_int,
+    xkey: XKeyEvent,
+    pad: [c_long; 24],
+}
+
+#[repr(C)]
+struct XErrorEvent {
+    type_: c_int,
+    display: Display,
+    resourceid: c_ulong,
+    serial: c_ulong,
+    error_code: c_uchar,
+    request_code: c_uchar,
+    minor_code: c_uchar,
+}
+
+#[link(name = "X11")]
+extern "C" {
+    fn XOpenDisplay(name: *const i8) -> Display;
+    fn XCloseDisplay(display: Display);
+    fn XDefaultRootWindow(display: Display) -> Window;
+    fn XGrabKey(
+        display: Display,
+        keycode: c_int,
+        modifiers: KeyButMask,
+        grab_window: Window,
+        owner_events: c_int,
+        pointer_mode: c_int,
+        keyboard_mode: c_int,
+    ) -> c_int;
+    fn XUngrabKey(
+        display: Display,
+        keycode: c_int,
+        modifiers: KeyButMask,
+        grab_window: Window,
+    ) -> c_int;
+    fn XAllowEvents(display: Display, event_mode: c_int, time: c_ulong) -> c_int;
+    fn XNextEvent(display: Display, event: *mut XEvent) -> c_int;
+    fn XSync(display: Display, discard: c_int) -> c_int;
+    fn XSetErrorHandler(
+        handler: Option<extern "C" fn(Display, *mut XErrorEvent) -> c_int>,
+    ) -> Option<extern "C" fn(Display, *mut XErrorEvent) -> c_int>;
+}
+
+// `XGrabKey` is a no-reply request: Xlib can't tell us synchronously whether
+// the server actually honoured it, so a failed grab (e.g. another client,
+// typically the window manager, already owns that combination) instead
+// surfaces as an asynchronous `BadAccess` error. We install a process-wide
+// error handler around the grab calls and `XSync` to force the server to
+// report it before we decide whether the grab succeeded.
+static GRAB_FAILED: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn record_grab_error(_display: Display, _event: *mut XErrorEvent) -> c_int {
+    GRAB_FAILED.store(true, Ordering::SeqCst);
+    0
+}
+
+const REPLAY_KEYBOARD: c_int = 5;
+const CURRENT_TIME: c_ulong = 0;
+
+/// An opaque handle identifying a registered hotkey, returned by
+/// [`Grabber::grab`] and accepted by [`Grabber::ungrab`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct HotkeyHandle(u64);
+
+struct Registration {
+    keycode: KeyCode,
+    modifiers: KeyButMask,
+}
+
+/// Listens for global hotkeys via `XGrabKey` and invokes a callback whenever
+/// a registered combination is pressed, without the event necessarily
+/// reaching other clients
+pub struct Grabber {
+    display: Display,
+    root: Window,
+    registrations: HashMap<HotkeyHandle, Registration>,
+    next_handle: u64,
+}
+// This is safe, we have a unique pointer.
+unsafe impl Send for Grabber {}
+
+impl Grabber {
+    /// Open a dedicated X connection for grabbing hotkeys
+    ///
+    /// # Errors
+    /// Returns an error if a connection to the X display could not be
+    /// established.
+    pub fn new() -> Result<Self, &'static str> {
+        let display = unsafe { XOpenDisplay(ptr::null()) };
+        if display.is_null() {
+            return Err("unable to open a connection to the X display");
+        }
+        let root = unsafe { XDefaultRootWindow(display) };
+        Ok(Self {
+            display,
+            root,
+            registrations: HashMap::new(),
+            next_handle: 0,
+        })
+    }
+
+    /// Register a global hotkey for the given keycode and modifier mask
+    /// (e.g. `Mod4Mask` for Super)
+    ///
+    /// Returns a handle that can later be passed to [`Grabber::ungrab`].
+    ///
+    /// # Errors
+    /// Returns an error if the combination could not be grabbed, for example
+    /// because another client already grabbed it.
+    pub fn grab(
+        &mut self,
+        keycode: KeyCode,
+        modifiers: KeyButMask,
+    ) -> Result<HotkeyHandle, &'static str> {
+        let mut grabbed = Vec::with_capacity(IGNORED_LOCKS.len());
+        let previous_handler = unsafe { XSetErrorHandler(Some(record_grab_error)) };
+        for ignored in IGNORED_LOCKS {
+            GRAB_FAILED.store(false, Ordering::SeqCst);
+            unsafe {
+                XGrabKey(
+                    self.display,
+                    keycode as c_int,
+                    modifiers | ignored,
+                    self.root,
+                    1,
+                    GRAB_MODE_ASYNC,
+                    GRAB_MODE_ASYNC,
+                );
+                XSync(self.display, 0);
+            }
+            if GRAB_FAILED.load(Ordering::SeqCst) {
+                for ignored in grabbed {
+                    unsafe {
+                        XUngrabKey(
+                            self.display,
+                            keycode as c_int,
+                            modifiers | ignored,
+                            self.root,
+                        );
+                    }
+                }
+                unsafe {
+                    XSetErrorHandler(previous_handler);
+                }
+                return Err("unable to grab the requested key combination");
+            }
+            grabbed.push(ignored);
+        }
+        unsafe {
+            XSetErrorHandler(previous_handler);
+        }
+
+        let handle = HotkeyHandle(self.next_handle);
+        self.next_handle += 1;
+        self.registrations
+            .insert(handle, Registration { keycode, modifiers });
+        Ok(handle)
+    }
+
+    /// Release a previously grabbed hotkey
+    pub fn ungrab(&mut self, handle: HotkeyHandle) {
+        if let Some(registration) = self.registrations.remove(&handle) {
+            for ignored in IGNORED_LOCKS {
+                unsafe {
+                    XUngrabKey(
+                        self.display,
+                        registration.keycode as c_int,
+                        registration.modifiers | ignored,
+                        self.root,
+                    );
+                }
+            }
+        }
+    }
+
+    /// Run the select-loop, invoking `on_event` with the handle of whichever
+    /// grabbed hotkey was just pressed
+    ///
+    /// The loop exits when `on_event` returns `false`, which can be used to
+    /// implement an escape-to-stop sentinel.
+    pub fn run(&self, mut on_event: impl FnMut(HotkeyHandle) -> bool) {
+        loop {
+            let mut event = unsafe { std::mem::zeroed::<XEvent>() };
+            unsafe {
+                XNextEvent(self.display, &mut event);
+            }
+            let key_event = unsafe { event.xkey };
+            if key_event._type != KEY_PRESS {
+                continue;
+            }
+            let state = key_event.state & !(LOCK_MASK | MOD2_MASK);
+            let handle = self.registrations.iter().find_map(|(handle, reg)| {
+                (reg.keycode == key_event.keycode && reg.modifiers == state).then_some(*handle)
+            });
+            unsafe {
+                XAllowEvents(self.display, REPLAY_KEYBOARD, CURRENT_TIME);
+            }
+            if let Some(handle) = handle {
+                if !on_event(handle) {
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Spawn the select-loop on a dedicated thread, forwarding every matched
+    /// hotkey press over a channel
+    #[must_use]
+    pub fn spawn(self) -> (JoinHandle<()>, mpsc::Receiver<HotkeyHandle>) {
+        let (tx, rx): (Sender<HotkeyHandle>, _) = mpsc::channel();
+        let thread = thread::spawn(move || {
+            self.run(|handle| tx.send(handle).is_ok());
+        });
+        (thread, rx)
+    }
+}
+
+impl Drop for Grabber {
+    fn drop(&mut self) {
+        let handles: Vec<_> = self.registrations.keys().copied().collect();
+        for handle in handles {
+            self.ungrab(handle);
+        }
+        unsafe {
+            XCloseDisplay(self.display);
+        }
+    }
+}
@@ -0,0 +1,133 @@
+use std::thread;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    Coordinate, Direction, InputError, InputResult, Key, KeyboardControllableNext, MouseButton,
+    MouseControllableNext,
+};
+
+use super::record::{CapturedEvent, TimedEvent};
+use super::xdo::Con;
+
+/// A single recorded input action, tagged with the delay (in microseconds)
+/// since the previous action was emitted
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MacroStep {
+    /// The action to replay
+    pub action: MacroAction,
+    /// The delay since the previous step, in microseconds
+    pub delay_us: u64,
+}
+
+/// An input action that can be stored in a [`Macro`] and replayed later
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum MacroAction {
+    /// Press, release or click a key
+    Key(Key, Direction),
+    /// Press, release or click a mouse button
+    Button(MouseButton, Direction),
+    /// Move the mouse, either to an absolute position or relative to its
+    /// current position
+    Move(i32, i32, Coordinate),
+}
+
+/// A recorded sequence of input actions with the inter-event timing
+/// preserved, so it can be replayed with the same cadence it was recorded
+/// with
+///
+/// Macros can be (de)serialized with `serde`, so they can be saved to disk
+/// and shared.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Macro {
+    steps: Vec<MacroStep>,
+}
+
+impl Macro {
+    /// Create an empty macro
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a step to the macro
+    pub fn push(&mut self, action: MacroAction, delay_us: u64) {
+        self.steps.push(MacroStep { action, delay_us });
+    }
+
+    /// The recorded steps, in order
+    #[must_use]
+    pub fn steps(&self) -> &[MacroStep] {
+        &self.steps
+    }
+
+    /// Build a macro out of events captured by a [`super::record::Recorder`],
+    /// deriving each step's delay from the difference between consecutive
+    /// `XServerTime` timestamps
+    #[must_use]
+    pub fn from_captured(events: &[TimedEvent]) -> Self {
+        let mut steps = Vec::with_capacity(events.len());
+        let mut last_timestamp = events.first().map(|e| e.timestamp);
+        for captured in events {
+            let delay_us =
+                last_timestamp.map_or(0, |last| captured.timestamp.saturating_sub(last) * 1000);
+            last_timestamp = Some(captured.timestamp);
+            if let Some(action) = to_macro_action(captured.event) {
+                steps.push(MacroStep { action, delay_us });
+            }
+        }
+        Self { steps }
+    }
+}
+
+fn to_macro_action(event: CapturedEvent) -> Option<MacroAction> {
+    match event {
+        CapturedEvent::Key(key, direction) => Some(MacroAction::Key(key, direction)),
+        CapturedEvent::Button(button, direction) => Some(MacroAction::Button(button, direction)),
+        CapturedEvent::MouseMove(x, y) => Some(MacroAction::Move(x, y, Coordinate::Absolute)),
+    }
+}
+
+impl Con {
+    /// Replay a previously recorded [`Macro`]
+    ///
+    /// `speed_multiplier` scales the delay between steps: `2.0` plays the
+    /// macro back twice as fast, `0.5` half as fast. `loop_count` repeats the
+    /// whole sequence that many times.
+    ///
+    /// # Errors
+    /// Returns an error if `speed_multiplier` isn't a positive, finite
+    /// number, or if any of the replayed actions fails to be emitted.
+    pub fn replay(
+        &mut self,
+        macro_: &Macro,
+        speed_multiplier: f64,
+        loop_count: u32,
+    ) -> InputResult<()> {
+        if !(speed_multiplier > 0.0) || !speed_multiplier.is_finite() {
+            return Err(InputError::InvalidInput(
+                "speed_multiplier must be a positive, finite number",
+            ));
+        }
+
+        for _ in 0..loop_count {
+            for step in macro_.steps() {
+                if step.delay_us > 0 {
+                    let scaled = (step.delay_us as f64 / speed_multiplier).max(0.0);
+                    thread::sleep(Duration::from_micros(scaled as u64));
+                }
+                match step.action {
+                    MacroAction::Key(key, direction) => self.enter_key(key, direction)?,
+                    MacroAction::Button(button, direction) => {
+                        self.send_mouse_button_event(button, direction, 0)?;
+                    }
+                    MacroAction::Move(x, y, coordinate) => {
+                        self.send_motion_notify_event(x, y, coordinate)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
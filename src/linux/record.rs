@@ -0,0 +1,314 @@
+use std::{
+    ffi::{c_int, c_uchar, c_uint, c_ulong},
+    ptr,
+    sync::mpsc::{self, Receiver},
+    thread::{self, JoinHandle},
+};
+
+use xkeysym::Keysym;
+
+use crate::{Direction, Key, MouseButton};
+
+type Display = *const c_void;
+type XRecordContext = c_ulong;
+type XRecordClientSpec = c_ulong;
+
+use std::ffi::c_void;
+
+const XRECORD_ALL_CLIENTS: XRecordClientSpec = 3; // XRecordAllClients
+const XRECORD_FROM_SERVER: c_int = 0; // XRecordFromServer
+
+const KEY_PRESS: c_int = 2;
+const KEY_RELEASE: c_int = 3;
+const BUTTON_PRESS: c_int = 4;
+const BUTTON_RELEASE: c_int = 5;
+const MOTION_NOTIFY: c_int = 6;
+
+const XRECORD_START_OF_DATA: c_int = 4;
+const XRECORD_END_OF_DATA: c_int = 5;
+
+#[repr(C)]
+struct XRecordRange {
+    core_requests: [c_uchar; 2],
+    core_replies: [c_uchar; 2],
+    // XRecordExtRange: an 8-bit major range followed by a 16-bit minor range
+    ext_requests: [c_uchar; 6],
+    ext_replies: [c_uchar; 6],
+    delivered_events: [c_uchar; 2],
+    device_events: [c_uchar; 2],
+    errors: [c_uchar; 2],
+    client_started: c_int,
+    client_died: c_int,
+}
+
+#[repr(C)]
+struct XRecordInterceptData {
+    id_base: c_ulong,
+    server_time: c_ulong,
+    client_seq: c_ulong,
+    category: c_int,
+    client_swapped: c_int,
+    data: *const c_uchar,
+    data_len: c_ulong,
+}
+
+#[link(name = "X11")]
+extern "C" {
+    fn XOpenDisplay(name: *const i8) -> Display;
+    fn XCloseDisplay(display: Display);
+    fn XKeycodeToKeysym(display: Display, keycode: c_uint, index: c_int) -> c_ulong;
+    fn XSync(display: Display, discard: c_int) -> c_int;
+}
+
+#[link(name = "Xtst")]
+extern "C" {
+    fn XRecordAllocRange() -> *mut XRecordRange;
+    fn XRecordCreateContext(
+        display: Display,
+        datum_flags: c_int,
+        clients: *const XRecordClientSpec,
+        nclients: c_int,
+        ranges: *mut *mut XRecordRange,
+        nranges: c_int,
+    ) -> XRecordContext;
+    fn XRecordEnableContext(
+        display: Display,
+        context: XRecordContext,
+        callback: extern "C" fn(*mut c_void, *mut XRecordInterceptData),
+        closure: *mut c_void,
+    ) -> c_int;
+    fn XRecordFreeContext(display: Display, context: XRecordContext) -> c_int;
+    fn XRecordFreeData(data: *mut XRecordInterceptData);
+    fn XRecordDisableContext(display: Display, context: XRecordContext) -> c_int;
+}
+
+/// A single input event captured by a [`Recorder`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CapturedEvent {
+    /// A key was pressed or released
+    Key(Key, Direction),
+    /// A mouse button was pressed or released
+    Button(MouseButton, Direction),
+    /// The mouse moved to the given root coordinates
+    MouseMove(i32, i32),
+}
+
+/// An input event together with the server timestamp (milliseconds) it
+/// occurred at
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimedEvent {
+    /// The captured event
+    pub event: CapturedEvent,
+    /// The `XServerTime` the event was recorded at, in milliseconds
+    pub timestamp: u64,
+}
+
+/// Captures keyboard and mouse events system-wide via the `XRecord`
+/// extension, independent of the [`super::xdo::Con`] used for emitting them
+pub struct Recorder {
+    /// The connection used to create/disable/free the context and to close
+    /// down the recorder; control operations must happen on a different
+    /// connection than the one blocked inside `XRecordEnableContext`.
+    control_display: Display,
+    context: XRecordContext,
+    receiver: Receiver<TimedEvent>,
+    thread: Option<JoinHandle<()>>,
+}
+// This is safe, we have a unique pointer: the control connection is only
+// used by whoever owns the `Recorder`, and the data-link connection only by
+// the dedicated recording thread.
+unsafe impl Send for Recorder {}
+
+/// Everything the recording thread's callback needs: the data-link display
+/// (to resolve keycodes to keysyms) and the channel to report events on.
+struct CallbackContext {
+    data_display: Display,
+    sender: mpsc::Sender<TimedEvent>,
+}
+
+extern "C" fn intercept_callback(closure: *mut c_void, data: *mut XRecordInterceptData) {
+    unsafe {
+        if data.is_null() {
+            return;
+        }
+        let category = (*data).category;
+        if category != XRECORD_FROM_SERVER {
+            XRecordFreeData(data);
+            return;
+        }
+
+        let ctx = &*(closure as *const CallbackContext);
+        if let Some(event) = parse_wire_event(ctx.data_display, &*data) {
+            let _ = ctx.sender.send(event);
+        }
+        XRecordFreeData(data);
+    }
+}
+
+unsafe fn parse_wire_event(display: Display, data: &XRecordInterceptData) -> Option<TimedEvent> {
+    // `data_len` counts 4-byte units; a core wire event is 32 bytes long.
+    if data.data.is_null() || data.data_len < 8 {
+        return None;
+    }
+    let bytes = std::slice::from_raw_parts(data.data, data.data_len as usize * 4);
+    let event_type = c_int::from(bytes[0] & 0x7f);
+    // detail (keycode/button) is the second byte; in the core wire
+    // protocol's xEvent union root_x/root_y live at offset 20/22 (eventX/
+    // eventY, the coordinates relative to the reporting window, sit at
+    // 24/26 instead).
+    let detail = bytes[1];
+    let root_x = i16::from_ne_bytes([bytes[20], bytes[21]]);
+    let root_y = i16::from_ne_bytes([bytes[22], bytes[23]]);
+
+    let event = match event_type {
+        KEY_PRESS | KEY_RELEASE => {
+            let keysym = XKeycodeToKeysym(display, c_uint::from(detail), 0);
+            let key = Keysym::new(keysym as u32).try_into().ok()?;
+            let direction = if event_type == KEY_PRESS {
+                Direction::Press
+            } else {
+                Direction::Release
+            };
+            CapturedEvent::Key(key, direction)
+        }
+        BUTTON_PRESS | BUTTON_RELEASE => {
+            let button = match detail {
+                1 => MouseButton::Left,
+                2 => MouseButton::Middle,
+                3 => MouseButton::Right,
+                4 => MouseButton::ScrollUp,
+                5 => MouseButton::ScrollDown,
+                6 => MouseButton::ScrollLeft,
+                7 => MouseButton::ScrollRight,
+                8 => MouseButton::Back,
+                9 => MouseButton::Forward,
+                _ => return None,
+            };
+            let direction = if event_type == BUTTON_PRESS {
+                Direction::Press
+            } else {
+                Direction::Release
+            };
+            CapturedEvent::Button(button, direction)
+        }
+        MOTION_NOTIFY => CapturedEvent::MouseMove(i32::from(root_x), i32::from(root_y)),
+        _ => return None,
+    };
+
+    Some(TimedEvent {
+        event,
+        timestamp: data.server_time,
+    })
+}
+
+impl Recorder {
+    /// Start recording all keyboard and mouse events on the X server, using
+    /// the `XRecord` extension
+    ///
+    /// Two connections are opened: a control connection used to
+    /// create/disable/free the context, and a second, data-link connection
+    /// that the recording thread blocks on inside `XRecordEnableContext`.
+    /// Without Xlib thread support enabled, issuing control calls on the
+    /// same connection a thread is blocked reading from is a documented
+    /// hazard (it hangs or corrupts the connection instead of stopping
+    /// cleanly), so the two must stay separate.
+    ///
+    /// # Errors
+    /// Returns an error if either connection to the X display could not be
+    /// established, or if the `XRecord` extension isn't available.
+    pub fn new() -> Result<Self, &'static str> {
+        let control_display = unsafe { XOpenDisplay(ptr::null()) };
+        if control_display.is_null() {
+            return Err("unable to open a control connection to the X display");
+        }
+
+        let data_display = unsafe { XOpenDisplay(ptr::null()) };
+        if data_display.is_null() {
+            unsafe { XCloseDisplay(control_display) };
+            return Err("unable to open a data-link connection to the X display");
+        }
+
+        let range = unsafe { XRecordAllocRange() };
+        if range.is_null() {
+            unsafe {
+                XCloseDisplay(data_display);
+                XCloseDisplay(control_display);
+            }
+            return Err("unable to allocate an XRecordRange");
+        }
+        unsafe {
+            (*range).device_events = [KEY_PRESS as c_uchar, MOTION_NOTIFY as c_uchar];
+        }
+
+        let clients = [XRECORD_ALL_CLIENTS];
+        let mut ranges = [range];
+        let context = unsafe {
+            XRecordCreateContext(
+                control_display,
+                0,
+                clients.as_ptr(),
+                1,
+                ranges.as_mut_ptr(),
+                1,
+            )
+        };
+        if context == 0 {
+            unsafe {
+                XCloseDisplay(data_display);
+                XCloseDisplay(control_display);
+            }
+            return Err("unable to create an XRecordContext");
+        }
+
+        let (tx, rx) = mpsc::channel();
+        let thread = thread::spawn(move || {
+            let ctx = Box::new(CallbackContext {
+                data_display,
+                sender: tx,
+            });
+            let closure = Box::into_raw(ctx) as *mut c_void;
+            unsafe {
+                XRecordEnableContext(data_display, context, intercept_callback, closure);
+                drop(Box::from_raw(closure as *mut CallbackContext));
+                XCloseDisplay(data_display);
+            }
+        });
+
+        Ok(Self {
+            control_display,
+            context,
+            receiver: rx,
+            thread: Some(thread),
+        })
+    }
+
+    /// Block until the next input event is captured
+    pub fn recv(&self) -> Option<TimedEvent> {
+        self.receiver.recv().ok()
+    }
+
+    /// Return an iterator that yields every captured event as it arrives
+    pub fn events(&self) -> impl Iterator<Item = TimedEvent> + '_ {
+        self.receiver.iter()
+    }
+}
+
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        unsafe {
+            // Disabling from the control connection unblocks the recording
+            // thread's call to `XRecordEnableContext` on the data-link
+            // connection; the thread closes that connection itself once it
+            // returns.
+            XRecordDisableContext(self.control_display, self.context);
+            XSync(self.control_display, 0);
+        }
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+        unsafe {
+            XRecordFreeContext(self.control_display, self.context);
+            XCloseDisplay(self.control_display);
+        }
+    }
+}
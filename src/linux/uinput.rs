@@ -0,0 +1,484 @@
+//! A Wayland-compatible backend that drives virtual input devices through
+//! the kernel `uinput` interface, since [`super::xdo`] only works on X11.
+//!
+//! Because `uinput` operates below the display server, this backend works
+//! on both X11 and Wayland sessions. [`should_use_uinput`] reports whether
+//! `WAYLAND_DISPLAY` is set; [`Con::try_default`] does not check it and
+//! always opens `/dev/uinput`.
+
+use std::{
+    fs::{File, OpenOptions},
+    mem::size_of,
+    os::fd::AsRawFd,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use libc::{c_int, c_long, c_short, c_uint, c_ushort, ioctl, timeval};
+
+use crate::{
+    Axis, Coordinate, Direction, InputError, InputResult, Key, KeyboardControllableNext,
+    MouseButton, MouseControllableNext, NewConError,
+};
+
+const UINPUT_MAX_NAME_SIZE: usize = 80;
+
+const EV_KEY: c_ushort = 0x01;
+const EV_REL: c_ushort = 0x02;
+const EV_SYN: c_ushort = 0x00;
+const SYN_REPORT: c_ushort = 0;
+
+const REL_X: c_ushort = 0x00;
+const REL_Y: c_ushort = 0x01;
+const REL_WHEEL: c_ushort = 0x08;
+const REL_HWHEEL: c_ushort = 0x06;
+
+const BTN_LEFT: c_ushort = 0x110;
+const BTN_RIGHT: c_ushort = 0x111;
+const BTN_MIDDLE: c_ushort = 0x112;
+const BTN_SIDE: c_ushort = 0x113;
+const BTN_EXTRA: c_ushort = 0x114;
+
+const UI_SET_EVBIT: c_ulong_ioctl = 0x4004_5564;
+const UI_SET_KEYBIT: c_ulong_ioctl = 0x4004_5565;
+const UI_SET_RELBIT: c_ulong_ioctl = 0x4004_5566;
+const UI_DEV_CREATE: c_ulong_ioctl = 0x5501;
+const UI_DEV_DESTROY: c_ulong_ioctl = 0x5502;
+
+#[allow(non_camel_case_types)]
+type c_ulong_ioctl = libc::c_ulong;
+
+#[repr(C)]
+struct input_id {
+    bustype: c_ushort,
+    vendor: c_ushort,
+    product: c_ushort,
+    version: c_ushort,
+}
+
+#[repr(C)]
+struct uinput_user_dev {
+    name: [u8; UINPUT_MAX_NAME_SIZE],
+    id: input_id,
+    ff_effects_max: c_uint,
+    absmax: [i32; 64],
+    absmin: [i32; 64],
+    absfuzz: [i32; 64],
+    absflat: [i32; 64],
+}
+
+#[repr(C)]
+struct input_event {
+    time: timeval,
+    kind: c_ushort,
+    code: c_ushort,
+    value: c_int,
+}
+
+fn mousebutton(button: MouseButton) -> Option<c_ushort> {
+    match button {
+        MouseButton::Left => Some(BTN_LEFT),
+        MouseButton::Right => Some(BTN_RIGHT),
+        MouseButton::Middle => Some(BTN_MIDDLE),
+        MouseButton::Back => Some(BTN_SIDE),
+        MouseButton::Forward => Some(BTN_EXTRA),
+        // Scroll "buttons" are emitted as relative wheel motion instead.
+        MouseButton::ScrollUp
+        | MouseButton::ScrollDown
+        | MouseButton::ScrollLeft
+        | MouseButton::ScrollRight => None,
+    }
+}
+
+/// The `KEY_LEFTSHIFT` code, reused whenever a character needs Shift held
+/// to be typed
+const KEY_LEFTSHIFT: c_ushort = 42;
+
+// Maps enigo's `Key` to the Linux `KEY_*` input-event code it corresponds
+// to. Keys without a sensible mapping (besides `Unicode`, handled
+// separately by `char_keycode`) are rejected with `InvalidInput`.
+fn keycode(key: Key) -> Option<c_ushort> {
+    use Key::{
+        Alt, Backspace, CapsLock, Control, Delete, DownArrow, End, Escape, Home, LeftArrow, Meta,
+        PageDown, PageUp, Return, RightArrow, Shift, Space, Tab, UpArrow, F1, F10, F11, F12, F2,
+        F3, F4, F5, F6, F7, F8, F9,
+    };
+    Some(match key {
+        Return => 28,
+        Escape => 1,
+        Backspace => 14,
+        Tab => 15,
+        Space => 57,
+        CapsLock => 58,
+        Shift => KEY_LEFTSHIFT,
+        Control => 29,
+        Alt => 56,
+        Meta => 125,
+        Home => 102,
+        End => 107,
+        Delete => 111,
+        PageUp => 104,
+        PageDown => 109,
+        UpArrow => 103,
+        DownArrow => 108,
+        LeftArrow => 105,
+        RightArrow => 106,
+        F1 => 59,
+        F2 => 60,
+        F3 => 61,
+        F4 => 62,
+        F5 => 63,
+        F6 => 64,
+        F7 => 65,
+        F8 => 66,
+        F9 => 67,
+        F10 => 68,
+        F11 => 87,
+        F12 => 88,
+        _ => return None,
+    })
+}
+
+/// Maps a printable character to the `KEY_*` code that produces it on a
+/// standard US QWERTY layout, plus whether Shift has to be held to reach it
+fn char_keycode(c: char) -> Option<(c_ushort, bool)> {
+    let lower_letter = |c: char| -> Option<c_ushort> {
+        Some(match c {
+            'a' => 30,
+            'b' => 48,
+            'c' => 46,
+            'd' => 32,
+            'e' => 18,
+            'f' => 33,
+            'g' => 34,
+            'h' => 35,
+            'i' => 23,
+            'j' => 36,
+            'k' => 37,
+            'l' => 38,
+            'm' => 50,
+            'n' => 49,
+            'o' => 24,
+            'p' => 25,
+            'q' => 16,
+            'r' => 19,
+            's' => 31,
+            't' => 20,
+            'u' => 22,
+            'v' => 47,
+            'w' => 17,
+            'x' => 45,
+            'y' => 21,
+            'z' => 44,
+            _ => return None,
+        })
+    };
+
+    if c.is_ascii_lowercase() {
+        return Some((lower_letter(c)?, false));
+    }
+    if c.is_ascii_uppercase() {
+        return Some((lower_letter(c.to_ascii_lowercase())?, true));
+    }
+
+    Some(match c {
+        '1' => (2, false),
+        '!' => (2, true),
+        '2' => (3, false),
+        '@' => (3, true),
+        '3' => (4, false),
+        '#' => (4, true),
+        '4' => (5, false),
+        '$' => (5, true),
+        '5' => (6, false),
+        '%' => (6, true),
+        '6' => (7, false),
+        '^' => (7, true),
+        '7' => (8, false),
+        '&' => (8, true),
+        '8' => (9, false),
+        '*' => (9, true),
+        '9' => (10, false),
+        '(' => (10, true),
+        '0' => (11, false),
+        ')' => (11, true),
+        '-' => (12, false),
+        '_' => (12, true),
+        '=' => (13, false),
+        '+' => (13, true),
+        '[' => (26, false),
+        '{' => (26, true),
+        ']' => (27, false),
+        '}' => (27, true),
+        '\\' => (43, false),
+        '|' => (43, true),
+        ';' => (39, false),
+        ':' => (39, true),
+        '\'' => (40, false),
+        '"' => (40, true),
+        '`' => (41, false),
+        '~' => (41, true),
+        ',' => (51, false),
+        '<' => (51, true),
+        '.' => (52, false),
+        '>' => (52, true),
+        '/' => (53, false),
+        '?' => (53, true),
+        ' ' => (57, false),
+        _ => return None,
+    })
+}
+
+/// The main struct for handling event emitting through a virtual `uinput`
+/// device
+pub struct Con {
+    file: File,
+}
+// This is safe, we have a unique file descriptor.
+unsafe impl Send for Con {}
+
+impl Con {
+    /// Create a new virtual input device through `/dev/uinput`
+    ///
+    /// # Errors
+    /// Returns an error if `/dev/uinput` cannot be opened (commonly a
+    /// permissions issue - the calling user needs to be in the `input`
+    /// group or have a matching udev rule) or if the device could not be
+    /// registered with the kernel.
+    pub fn try_default() -> Result<Self, NewConError> {
+        let file = OpenOptions::new()
+            .write(true)
+            .open("/dev/uinput")
+            .map_err(|_| NewConError::EstablishCon("unable to open /dev/uinput"))?;
+        let fd = file.as_raw_fd();
+
+        unsafe {
+            ioctl(fd, UI_SET_EVBIT as _, EV_KEY);
+            ioctl(fd, UI_SET_EVBIT as _, EV_REL);
+            for key in 1..248u16 {
+                ioctl(fd, UI_SET_KEYBIT as _, c_int::from(key));
+            }
+            // The mouse buttons live outside the standard keyboard key-code
+            // range the loop above covers, so each has to be registered
+            // explicitly or the kernel rejects/drops their EV_KEY events.
+            for button in [BTN_LEFT, BTN_RIGHT, BTN_MIDDLE, BTN_SIDE, BTN_EXTRA] {
+                ioctl(fd, UI_SET_KEYBIT as _, c_int::from(button));
+            }
+            ioctl(fd, UI_SET_RELBIT as _, REL_X);
+            ioctl(fd, UI_SET_RELBIT as _, REL_Y);
+            ioctl(fd, UI_SET_RELBIT as _, REL_WHEEL);
+            ioctl(fd, UI_SET_RELBIT as _, REL_HWHEEL);
+        }
+
+        let mut name = [0u8; UINPUT_MAX_NAME_SIZE];
+        let device_name = b"enigo-virtual-input";
+        name[..device_name.len()].copy_from_slice(device_name);
+
+        let dev = uinput_user_dev {
+            name,
+            id: input_id {
+                bustype: 0x03, // BUS_USB
+                vendor: 0x1,
+                product: 0x1,
+                version: 1,
+            },
+            ff_effects_max: 0,
+            absmax: [0; 64],
+            absmin: [0; 64],
+            absfuzz: [0; 64],
+            absflat: [0; 64],
+        };
+        write_struct(fd, &dev).map_err(|_| {
+            NewConError::EstablishCon("unable to write the uinput device descriptor")
+        })?;
+
+        let res = unsafe { ioctl(fd, UI_DEV_CREATE as _) };
+        if res < 0 {
+            return Err(NewConError::EstablishCon(
+                "the kernel rejected creation of the uinput device",
+            ));
+        }
+
+        Ok(Self { file })
+    }
+
+    fn emit(&mut self, kind: c_ushort, code: c_ushort, value: i32) -> InputResult<()> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let event = input_event {
+            time: timeval {
+                tv_sec: now.as_secs() as c_long,
+                tv_usec: now.subsec_micros() as c_long,
+            },
+            kind,
+            code,
+            value,
+        };
+        write_struct(self.file.as_raw_fd(), &event)
+            .map_err(|_| InputError::Simulate("unable to write the input_event to /dev/uinput"))
+    }
+
+    fn sync(&mut self) -> InputResult<()> {
+        self.emit(EV_SYN, SYN_REPORT, 0)
+    }
+
+    fn key_event(&mut self, code: c_ushort, direction: Direction) -> InputResult<()> {
+        match direction {
+            Direction::Press => {
+                self.emit(EV_KEY, code, 1)?;
+                self.sync()
+            }
+            Direction::Release => {
+                self.emit(EV_KEY, code, 0)?;
+                self.sync()
+            }
+            Direction::Click => {
+                self.emit(EV_KEY, code, 1)?;
+                self.sync()?;
+                self.emit(EV_KEY, code, 0)?;
+                self.sync()
+            }
+        }
+    }
+
+    /// Press or release `code`, holding Shift around it first if it needs
+    /// Shift to produce the requested character
+    fn key_event_shifted(
+        &mut self,
+        code: c_ushort,
+        needs_shift: bool,
+        direction: Direction,
+    ) -> InputResult<()> {
+        if needs_shift && matches!(direction, Direction::Press | Direction::Click) {
+            self.key_event(KEY_LEFTSHIFT, Direction::Press)?;
+        }
+        self.key_event(code, direction)?;
+        if needs_shift && matches!(direction, Direction::Release | Direction::Click) {
+            self.key_event(KEY_LEFTSHIFT, Direction::Release)?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Con {
+    fn drop(&mut self) {
+        unsafe {
+            ioctl(self.file.as_raw_fd(), UI_DEV_DESTROY as _);
+        }
+    }
+}
+
+fn write_struct<T>(fd: c_int, value: &T) -> std::io::Result<()> {
+    let bytes =
+        unsafe { std::slice::from_raw_parts((value as *const T).cast::<u8>(), size_of::<T>()) };
+    let written = unsafe { libc::write(fd, bytes.as_ptr().cast(), bytes.len()) };
+    if written as usize != bytes.len() {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+impl KeyboardControllableNext for Con {
+    fn fast_text_entry(&mut self, _text: &str) -> InputResult<Option<()>> {
+        // uinput has no notion of a keysym; the caller falls back to
+        // per-character `enter_key` calls.
+        Ok(None)
+    }
+
+    fn enter_key(&mut self, key: Key, direction: Direction) -> InputResult<()> {
+        if let Key::Unicode(c) = key {
+            let Some((code, needs_shift)) = char_keycode(c) else {
+                return Err(InputError::InvalidInput(
+                    "this character has no known uinput KEY_* mapping for the US QWERTY layout",
+                ));
+            };
+            return self.key_event_shifted(code, needs_shift, direction);
+        }
+
+        let Some(code) = keycode(key) else {
+            return Err(InputError::InvalidInput(
+                "this key has no known uinput KEY_* mapping",
+            ));
+        };
+        self.key_event(code, direction)
+    }
+}
+
+impl MouseControllableNext for Con {
+    fn send_mouse_button_event(
+        &mut self,
+        button: MouseButton,
+        direction: Direction,
+        _: u32,
+    ) -> InputResult<()> {
+        match mousebutton(button) {
+            Some(code) => self.key_event(code, direction),
+            // A scroll "button" only has a press-edge in the real world: one
+            // `REL_WHEEL`/`REL_HWHEEL` tick per notch. Emitting a tick for
+            // both Press and Release (as `Direction::Click` implies) would
+            // double-scroll compared to the other backends, which treat
+            // scroll buttons as ordinary buttons whose release is a no-op.
+            None if direction == Direction::Release => Ok(()),
+            None => match button {
+                MouseButton::ScrollUp => self.mouse_scroll_event(-1, Axis::Vertical),
+                MouseButton::ScrollDown => self.mouse_scroll_event(1, Axis::Vertical),
+                MouseButton::ScrollLeft => self.mouse_scroll_event(-1, Axis::Horizontal),
+                _ => self.mouse_scroll_event(1, Axis::Horizontal),
+            },
+        }
+    }
+
+    fn send_motion_notify_event(
+        &mut self,
+        x: i32,
+        y: i32,
+        coordinate: Coordinate,
+    ) -> InputResult<()> {
+        // The device is only set up with relative (`REL_X`/`REL_Y`) axes, so
+        // an absolute move can't be honored; silently reinterpreting it as
+        // relative would land the cursor somewhere else on screen entirely,
+        // so report the limitation instead.
+        match coordinate {
+            Coordinate::Absolute => {
+                return Err(InputError::Simulate(
+                    "the uinput backend only supports relative mouse movement",
+                ))
+            }
+            Coordinate::Relative => {
+                self.emit(EV_REL, REL_X, x)?;
+                self.emit(EV_REL, REL_Y, y)?;
+                self.sync()
+            }
+        }
+    }
+
+    fn mouse_scroll_event(&mut self, length: i32, axis: Axis) -> InputResult<()> {
+        // A positive `length` scrolls down/right, as in the other backends;
+        // `REL_WHEEL` counts upward ticks as positive, so it is negated.
+        let (code, value) = match axis {
+            Axis::Vertical => (REL_WHEEL, -length),
+            Axis::Horizontal => (REL_HWHEEL, length),
+        };
+        self.emit(EV_REL, code, value)?;
+        self.sync()
+    }
+
+    fn main_display(&self) -> InputResult<(i32, i32)> {
+        Err(InputError::Simulate(
+            "uinput operates below the display server and has no notion of a display size",
+        ))
+    }
+
+    fn mouse_loc(&self) -> InputResult<(i32, i32)> {
+        Err(InputError::Simulate(
+            "uinput is a write-only virtual device; it cannot report the pointer location",
+        ))
+    }
+}
+
+/// Whether the `uinput` backend should be preferred over the X11 backends,
+/// i.e. a Wayland session is running
+#[must_use]
+pub fn should_use_uinput() -> bool {
+    std::env::var_os("WAYLAND_DISPLAY").is_some()
+}
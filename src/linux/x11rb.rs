@@ -0,0 +1,325 @@
+//! A pure-Rust X11 backend built on `x11rb`, used in place of [`super::xdo`]
+//! when the `xdo` FFI dependency is not wanted.
+//!
+//! It implements the exact same [`KeyboardControllableNext`] /
+//! [`MouseControllableNext`] traits, so it is a drop-in replacement: no
+//! native `libxdo` build dependency, and the ability to pipeline a batch of
+//! events behind a single [`Con::flush`] instead of one flush per event.
+
+use std::collections::HashMap;
+
+use x11rb::{
+    connection::Connection,
+    protocol::{
+        xproto::{ConnectionExt as _, Screen},
+        xtest::ConnectionExt as _,
+    },
+    rust_connection::RustConnection,
+};
+use xkeysym::Keysym;
+
+use crate::{
+    Axis, Coordinate, Direction, InputError, InputResult, Key, KeyboardControllableNext,
+    MouseButton, MouseControllableNext, NewConError,
+};
+
+const FAKE_KEY_PRESS: u8 = 2; // matches the core KeyPress event code, as xtest::fake_input expects
+const FAKE_KEY_RELEASE: u8 = 3;
+const FAKE_BUTTON_PRESS: u8 = 4;
+const FAKE_BUTTON_RELEASE: u8 = 5;
+const FAKE_MOTION_NOTIFY: u8 = 6;
+
+fn mousebutton(button: MouseButton) -> u8 {
+    match button {
+        MouseButton::Left => 1,
+        MouseButton::Middle => 2,
+        MouseButton::Right => 3,
+        MouseButton::ScrollUp => 4,
+        MouseButton::ScrollDown => 5,
+        MouseButton::ScrollLeft => 6,
+        MouseButton::ScrollRight => 7,
+        MouseButton::Back => 8,
+        MouseButton::Forward => 9,
+    }
+}
+
+/// The main struct for handling the event emitting, backed by `x11rb`
+/// instead of `libxdo`
+pub struct Con {
+    conn: RustConnection,
+    screen: usize,
+    root: u32,
+    delay: u32, // microseconds
+    // Each entry is the keycode a keysym is bound to, plus the shift level
+    // (keyboard mapping column) it lives at: 0 for the plain key, 1 for the
+    // shifted one (capitals, `!@#$...`). A level above 0 has to be typed
+    // with Shift held, or the wrong character comes out.
+    keycode_cache: HashMap<Keysym, (u8, u8)>,
+    shift_keycode: Option<u8>,
+    batching: bool,
+}
+
+impl Con {
+    /// Create a new Enigo instance
+    /// If no `dyp_name` is provided, the $DISPLAY environment variable is
+    /// read and used instead
+    fn new(dyp_name: Option<&str>, delay: u32) -> Result<Self, NewConError> {
+        let (conn, screen) = x11rb::connect(dyp_name)
+            .map_err(|_| NewConError::EstablishCon("unable to connect to the X11 display"))?;
+
+        conn.extension_information(x11rb::protocol::xtest::X11_EXTENSION_NAME)
+            .map_err(|_| NewConError::EstablishCon("unable to query the XTEST extension"))?
+            .ok_or(NewConError::EstablishCon(
+                "the XTEST extension is not available on this X server",
+            ))?;
+
+        let root = conn.setup().roots[screen].root;
+
+        Ok(Self {
+            conn,
+            screen,
+            root,
+            delay: delay * 1000,
+            keycode_cache: HashMap::new(),
+            shift_keycode: None,
+            batching: false,
+        })
+    }
+
+    /// Tries to establish a new X11 connection using default parameters
+    ///
+    /// # Errors
+    /// TODO
+    pub fn try_default() -> Result<Self, NewConError> {
+        Self::new(None, super::xdo::DEFAULT_DELAY)
+    }
+
+    /// Get the delay per keypress in milliseconds.
+    #[must_use]
+    pub fn delay(&self) -> u32 {
+        self.delay / 1000
+    }
+
+    /// Set the delay per keypress in milliseconds.
+    /// The value is stored and returned by [`Con::delay`]; this backend
+    /// sends events without waiting between keypresses.
+    pub fn set_delay(&mut self, delay: u32) {
+        self.delay = delay * 1000;
+    }
+
+    /// Start a batch: subsequent events are queued on the connection instead
+    /// of being flushed to the X server one by one. Call [`Con::flush`] to
+    /// send everything at once and wait for the server to catch up. This
+    /// turns a long `fast_text_entry` string from one flush per event into
+    /// a single one.
+    pub fn begin_batch(&mut self) {
+        self.batching = true;
+    }
+
+    /// Flush any events queued since [`Con::begin_batch`] and wait for the
+    /// server to process them
+    ///
+    /// # Errors
+    /// Returns an error if the connection to the X server was lost.
+    pub fn flush(&mut self) -> InputResult<()> {
+        self.batching = false;
+        self.conn
+            .flush()
+            .map_err(|_| InputError::Simulate("unable to flush queued X11 requests"))?;
+        self.conn
+            .sync()
+            .map_err(|_| InputError::Simulate("lost connection to the X server while flushing"))
+    }
+
+    fn fake_input(&mut self, kind: u8, detail: u32, x: i32, y: i32) -> InputResult<()> {
+        self.conn
+            .xtest_fake_input(
+                kind,
+                detail as u8,
+                x11rb::CURRENT_TIME,
+                self.root,
+                x as i16,
+                y as i16,
+                0,
+            )
+            .map_err(|_| InputError::Simulate("unable to queue the fake input event"))?;
+        if !self.batching {
+            self.conn
+                .flush()
+                .map_err(|_| InputError::Simulate("unable to send the fake input event"))?;
+        }
+        Ok(())
+    }
+
+    /// Resolve a `Keysym` to the keycode it is currently bound to and the
+    /// shift level (keymap column) it lives at, querying the server's
+    /// keymap the first time and caching the result
+    fn keycode_for(&mut self, keysym: Keysym) -> InputResult<(u8, u8)> {
+        if let Some(&entry) = self.keycode_cache.get(&keysym) {
+            return Ok(entry);
+        }
+
+        let setup = self.conn.setup();
+        let min_keycode = setup.min_keycode;
+        let max_keycode = setup.max_keycode;
+        let count = max_keycode - min_keycode + 1;
+        let mapping = self
+            .conn
+            .get_keyboard_mapping(min_keycode, count)
+            .map_err(|_| InputError::Simulate("unable to query the X11 keymap"))?
+            .reply()
+            .map_err(|_| InputError::Simulate("unable to query the X11 keymap"))?;
+
+        let per_keycode = mapping.keysyms_per_keycode as usize;
+        for (i, chunk) in mapping.keysyms.chunks(per_keycode).enumerate() {
+            if let Some(level) = chunk.iter().position(|&sym| sym == u32::from(keysym)) {
+                let keycode = min_keycode + i as u8;
+                let entry = (keycode, level as u8);
+                self.keycode_cache.insert(keysym, entry);
+                return Ok(entry);
+            }
+        }
+        Err(InputError::InvalidInput(
+            "the requested keysym is not bound to any keycode on this keymap",
+        ))
+    }
+
+    /// Resolve the keycode `Shift_L` is bound to, caching the result
+    fn shift_keycode(&mut self) -> InputResult<u8> {
+        if let Some(keycode) = self.shift_keycode {
+            return Ok(keycode);
+        }
+        let keysym = Keysym::from(xkeysym::key::Shift_L);
+        let (keycode, _level) = self.keycode_for(keysym)?;
+        self.shift_keycode = Some(keycode);
+        Ok(keycode)
+    }
+
+    /// Press or release the given keycode, holding Shift around it first if
+    /// the keysym lives at a non-zero shift level (capitals, `!@#$...`)
+    fn emit_key(&mut self, keycode: u8, level: u8, direction: Direction) -> InputResult<()> {
+        let needs_shift = level > 0;
+        if needs_shift && matches!(direction, Direction::Press | Direction::Click) {
+            let shift = self.shift_keycode()?;
+            self.fake_input(FAKE_KEY_PRESS, u32::from(shift), 0, 0)?;
+        }
+
+        match direction {
+            Direction::Press => self.fake_input(FAKE_KEY_PRESS, u32::from(keycode), 0, 0)?,
+            Direction::Release => self.fake_input(FAKE_KEY_RELEASE, u32::from(keycode), 0, 0)?,
+            Direction::Click => {
+                self.fake_input(FAKE_KEY_PRESS, u32::from(keycode), 0, 0)?;
+                self.fake_input(FAKE_KEY_RELEASE, u32::from(keycode), 0, 0)?;
+            }
+        }
+
+        if needs_shift && matches!(direction, Direction::Release | Direction::Click) {
+            let shift = self.shift_keycode()?;
+            self.fake_input(FAKE_KEY_RELEASE, u32::from(shift), 0, 0)?;
+        }
+        Ok(())
+    }
+}
+
+impl KeyboardControllableNext for Con {
+    fn fast_text_entry(&mut self, text: &str) -> InputResult<Option<()>> {
+        self.begin_batch();
+        let result = (|| {
+            for c in text.chars() {
+                let Ok(keysym) = Keysym::try_from(c) else {
+                    continue;
+                };
+                let (keycode, level) = self.keycode_for(keysym)?;
+                self.emit_key(keycode, level, Direction::Click)?;
+            }
+            Ok(())
+        })();
+        // `flush` is also what clears `self.batching`; run it on every exit
+        // path; an error partway through the loop must not leave later
+        // single-shot calls silently queuing forever.
+        self.flush()?;
+        result?;
+        Ok(Some(()))
+    }
+
+    fn enter_key(&mut self, key: Key, direction: Direction) -> InputResult<()> {
+        let Ok(keysym) = Keysym::try_from(key) else {
+            return Err(InputError::InvalidInput(
+                "you can't enter a raw keycode with this backend",
+            ));
+        };
+        let (keycode, level) = self.keycode_for(keysym)?;
+        self.emit_key(keycode, level, direction)
+    }
+}
+
+impl MouseControllableNext for Con {
+    fn send_mouse_button_event(
+        &mut self,
+        button: MouseButton,
+        direction: Direction,
+        _: u32,
+    ) -> InputResult<()> {
+        let detail = u32::from(mousebutton(button));
+        match direction {
+            Direction::Press => self.fake_input(FAKE_BUTTON_PRESS, detail, 0, 0),
+            Direction::Release => self.fake_input(FAKE_BUTTON_RELEASE, detail, 0, 0),
+            Direction::Click => {
+                self.fake_input(FAKE_BUTTON_PRESS, detail, 0, 0)?;
+                self.fake_input(FAKE_BUTTON_RELEASE, detail, 0, 0)
+            }
+        }
+    }
+
+    fn send_motion_notify_event(
+        &mut self,
+        x: i32,
+        y: i32,
+        coordinate: Coordinate,
+    ) -> InputResult<()> {
+        // XTEST's fake motion event takes a `detail` of 1 for a relative
+        // move and 0 for an absolute one (on the root window).
+        match coordinate {
+            Coordinate::Relative => self.fake_input(FAKE_MOTION_NOTIFY, 1, x, y),
+            Coordinate::Absolute => self.fake_input(FAKE_MOTION_NOTIFY, 0, x, y),
+        }
+    }
+
+    fn mouse_scroll_event(&mut self, length: i32, axis: Axis) -> InputResult<()> {
+        let mut length = length;
+        let button = if length < 0 {
+            length = -length;
+            match axis {
+                Axis::Horizontal => MouseButton::ScrollLeft,
+                Axis::Vertical => MouseButton::ScrollUp,
+            }
+        } else {
+            match axis {
+                Axis::Horizontal => MouseButton::ScrollRight,
+                Axis::Vertical => MouseButton::ScrollDown,
+            }
+        };
+        for _ in 0..length {
+            self.send_mouse_button_event(button, Direction::Click, 0)?;
+        }
+        Ok(())
+    }
+
+    fn main_display(&self) -> InputResult<(i32, i32)> {
+        let screen: &Screen = &self.conn.setup().roots[self.screen];
+        Ok((
+            i32::from(screen.width_in_pixels),
+            i32::from(screen.height_in_pixels),
+        ))
+    }
+
+    fn mouse_loc(&self) -> InputResult<(i32, i32)> {
+        let pointer = self
+            .conn
+            .query_pointer(self.root)
+            .map_err(|_| InputError::Simulate("unable to query the pointer position"))?
+            .reply()
+            .map_err(|_| InputError::Simulate("unable to query the pointer position"))?;
+        Ok((i32::from(pointer.root_x), i32::from(pointer.root_y)))
+    }
+}
@@ -1,4 +1,4 @@
-use std::{ffi::CString, ptr};
+use std::{ffi::CString, ptr, thread, time::Duration};
 
 use libc::{c_char, c_int, c_ulong, c_void, useconds_t};
 
@@ -9,7 +9,7 @@ use crate::{
 use xkeysym::Keysym;
 
 const CURRENT_WINDOW: c_ulong = 0;
-const DEFAULT_DELAY: u32 = 12; // milliseconds
+pub(crate) const DEFAULT_DELAY: u32 = 12; // milliseconds
 const XDO_SUCCESS: c_int = 0;
 
 type Window = c_ulong;
@@ -85,6 +85,11 @@ fn mousebutton(button: MouseButton) -> c_int {
 pub struct Con {
     xdo: Xdo,
     delay: u32, // microseconds
+    // The part of the last fractional target position that didn't fit in
+    // the integer pixel `xdo_move_mouse` was actually given, carried
+    // forward (in the same 24.8 fixed-point units as `to_fixed`) so it
+    // isn't simply discarded on the next `move_mouse_fractional` call.
+    frac_remainder: (i32, i32),
 }
 // This is safe, we have a unique pointer.
 // TODO: use Unique<c_char> once stable.
@@ -116,6 +121,7 @@ impl Con {
         Ok(Self {
             xdo,
             delay: delay * 1000,
+            frac_remainder: (0, 0),
         })
     }
     /// Tries to establish a new X11 connection using default parameters
@@ -139,7 +145,68 @@ impl Con {
     pub fn set_delay(&mut self, delay: u32) {
         self.delay = delay * 1000;
     }
+
+    /// Move the mouse to a fractional absolute position.
+    ///
+    /// `xdo_move_mouse` only understands integer pixel coordinates, so `x`
+    /// and `y` are converted through the same 24.8 fixed-point
+    /// representation Wayland uses for `wl_fixed` (the value × 256). Simply
+    /// rounding each call independently would throw away whatever didn't
+    /// fit in the emitted pixel every time, so the leftover sub-pixel
+    /// amount is instead carried forward into the next call (in `Con`'s
+    /// `frac_remainder`) and added back in — the same error-diffusion trick
+    /// used to dither a line across a low-resolution grid. That keeps a
+    /// sequence of fractional moves tracking the intended position on
+    /// average, rather than always rounding the same way.
+    pub fn move_mouse_fractional(&mut self, x: f64, y: f64) -> InputResult<()> {
+        let target_x = to_fixed(x) + self.frac_remainder.0;
+        let target_y = to_fixed(y) + self.frac_remainder.1;
+        let (px, py) = (target_x.div_euclid(256), target_y.div_euclid(256));
+        self.frac_remainder = (target_x - px * 256, target_y - py * 256);
+        self.send_motion_notify_event(px, py, Coordinate::Absolute)
+    }
+
+    /// Move the mouse from its current position to `(x, y)` along a smooth,
+    /// human-like path instead of jumping there instantly.
+    ///
+    /// The path is interpolated linearly into `steps` intermediate
+    /// positions, each emitted `duration / steps` apart.
+    ///
+    /// # Errors
+    /// Returns an error if the current mouse position can't be read, or if
+    /// any intermediate move fails.
+    pub fn move_mouse_smooth(
+        &mut self,
+        x: f64,
+        y: f64,
+        duration: Duration,
+        steps: u32,
+    ) -> InputResult<()> {
+        let steps = steps.max(1);
+        let (start_x, start_y) = self.mouse_loc()?;
+        let (start_x, start_y) = (f64::from(start_x), f64::from(start_y));
+        let step_delay = duration / steps;
+
+        for step in 1..=steps {
+            let t = f64::from(step) / f64::from(steps);
+            let ix = start_x + (x - start_x) * t;
+            let iy = start_y + (y - start_y) * t;
+            self.move_mouse_fractional(ix, iy)?;
+            if step != steps {
+                thread::sleep(step_delay);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Converts a coordinate to the Wayland-style 24.8 fixed-point
+/// representation (value × 256, as done when converting `wl_fixed` to a
+/// double) used internally to keep fractional precision between calls.
+fn to_fixed(value: f64) -> i32 {
+    (value * 256.0).round() as i32
 }
+
 impl Drop for Con {
     fn drop(&mut self) {
         unsafe {